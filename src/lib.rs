@@ -1,14 +1,30 @@
 use std::fmt::Debug;
 
+/// One row of an exact cover matrix: its label, and the `(col, color)` pairs it
+/// touches. `color == 0` means uncolored.
+pub type ExactCoverRow<L> = (L, Vec<(usize, usize)>);
+
 pub trait ExactCover
 {
     type Label: Copy + Debug;
 
-    fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (Self::Label, Vec<usize>)> + 'a>;
+    fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = ExactCoverRow<Self::Label>> + 'a>;
     fn exact_cover_num_cols(&self) -> usize;
+
+    /// Number of columns, counted back from the end of `exact_cover_num_cols`, that are
+    /// secondary: covered at most once instead of exactly once. Defaults to zero.
+    fn exact_cover_secondary_cols(&self) -> usize {
+        0
+    }
+
+    /// Cost of including this row in a solution, used by `DancingLinks::solve_min_weight`.
+    fn exact_cover_row_weight(&self, _label: &Self::Label) -> u64 {
+        1
+    }
 }
 
 pub mod sudoku;
+pub mod nonogram;
 
 #[derive(Default, Clone, Copy, Debug)]
 struct Node {
@@ -17,7 +33,14 @@ struct Node {
     u: usize,
     d: usize,
     col: usize,
+    color: usize,
     data: usize,
+    // Only meaningful on secondary column headers: how many currently-committed rows
+    // have purified this column. Purifying only hides disagreeing rows, and unpurifying
+    // only restores them, on the 0<->1 transition, so a column shared by more than one
+    // agreeing row in the same branch (as happens when two different lines both touch
+    // the same cell in the nonogram reduction) is hidden and restored exactly once.
+    purify_count: usize,
 }
 
 pub struct DancingLinks<L>
@@ -26,7 +49,9 @@ where
 {
     node_list: Vec<Node>,
     num_cols: usize,
-    row_labels: Vec<L>
+    num_secondary_cols: usize,
+    row_labels: Vec<L>,
+    row_weights: Vec<u64>,
 }
 
 impl<L> DancingLinks<L>
@@ -38,19 +63,25 @@ where
         EC: ExactCover<Label = L>
     {
         let num_cols = ec.exact_cover_num_cols();
+        let num_secondary_cols = ec.exact_cover_secondary_cols();
         let node_list = Vec::new();
         let row_labels = Vec::new();
+        let row_weights = Vec::new();
 
         let mut dl = DancingLinks {
             node_list,
             num_cols,
+            num_secondary_cols,
             row_labels,
+            row_weights,
         };
 
         dl.setup_headers();
 
         for row in ec.exact_cover_rows() {
+            let weight = ec.exact_cover_row_weight(&row.0);
             dl.add_row(row);
+            dl.row_weights.push(weight);
         }
 
         dl.remove_empty_cols();
@@ -62,25 +93,41 @@ where
         dl
     }
 
+    fn num_primary_cols(&self) -> usize {
+        self.num_cols - self.num_secondary_cols
+    }
+
     fn setup_headers(&mut self) {
+        let num_cols = self.num_cols;
+        let num_primary = self.num_primary_cols();
         let root = Node {
             col: 0x51deb00b,
             data: 0x51deb00b,
-            l: self.num_cols,
-            r: 1,
+            color: 0,
+            purify_count: 0,
+            l: if num_primary == 0 { 0 } else { num_primary },
+            r: if num_primary == 0 { 0 } else { 1 },
             u: 0,
             d: 0,
         };
         self.node_list.push(root);
-        let num_cols = self.num_cols;
         self.node_list.extend((0..num_cols).map(|i| {
+            let pos = i + 1;
+            // secondary headers are self-looped so choose_col never lands on them
+            let (l, r) = if i < num_primary {
+                (i, if i + 1 < num_primary { i + 2 } else { 0 })
+            } else {
+                (pos, pos)
+            };
             Node {
-                l: i,
-                r: (i + 2) % (num_cols + 1),
-                u: i + 1,
-                d: i + 1,
+                l,
+                r,
+                u: pos,
+                d: pos,
                 col: i,
+                color: 0,
                 data: 0,
+                purify_count: 0,
             }
         }));
     }
@@ -92,13 +139,13 @@ where
         col + 1
     }
 
-    fn add_row(&mut self, (label, row): (L, Vec<usize>)) {
+    fn add_row(&mut self, (label, row): ExactCoverRow<L>) {
         let row_num = self.row_labels.len();
         self.row_labels.push(label);
         let mut idx = self.node_list.len();
         let row_start = idx;
         let row_len = row.len();
-        for (i, &col) in row.iter().enumerate() {
+        for (i, &(col, color)) in row.iter().enumerate() {
             if col >= self.num_cols {
                 panic!("row labeled {:?} exceeded column bounds: {}", label, col);
             }
@@ -109,7 +156,9 @@ where
                 u: self.node_list[header].u,
                 d: header,
                 col: header,
+                color,
                 data: row_num,
+                purify_count: 0,
             };
             self.node_list[new_node.u].d = idx;
             self.node_list[header].u = idx;
@@ -144,7 +193,7 @@ where
                 self.node_list[node.d].u = node.u;
                 self.node_list[node.u].d = node.d;
                 self.node_list[node.col].data -= 1;
-                
+
                 j = node.r;
             }
             i = self.node_list[i].d;
@@ -172,41 +221,267 @@ where
         self.node_list[header_node.r].l = header_idx;
     }
 
+    fn hide_row(&mut self, p: usize) {
+        let mut j = self.node_list[p].r;
+        while j != p {
+            let node = self.node_list[j];
+            self.node_list[node.u].d = node.d;
+            self.node_list[node.d].u = node.u;
+            self.node_list[node.col].data -= 1;
+            j = node.r;
+        }
+    }
+
+    fn unhide_row(&mut self, p: usize) {
+        let mut j = self.node_list[p].l;
+        while j != p {
+            let node = self.node_list[j];
+            self.node_list[node.col].data += 1;
+            self.node_list[node.u].d = j;
+            self.node_list[node.d].u = j;
+            j = node.l;
+        }
+    }
+
+    /// Purify the secondary column of `p`: commit its color to `p`'s color and hide
+    /// every other row in that column whose node disagrees on the color, leaving the
+    /// column header itself in place (it is never unlinked from the vertical chain).
+    fn purify(&mut self, p: usize) {
+        let node = self.node_list[p];
+        let c = node.col;
+        // A column already purified by an earlier, agreeing commit in this branch has
+        // already hidden every disagreeing row; redoing that scan would hide them a
+        // second time, so only the first purifier for a column actually hides anything.
+        if self.node_list[c].purify_count == 0 {
+            self.node_list[c].color = node.color;
+            let mut q = self.node_list[c].d;
+            while q != c {
+                if self.node_list[q].color != node.color {
+                    self.hide_row(q);
+                }
+                q = self.node_list[q].d;
+            }
+        }
+        self.node_list[c].purify_count += 1;
+    }
+
+    fn unpurify(&mut self, p: usize) {
+        let node = self.node_list[p];
+        let c = node.col;
+        self.node_list[c].purify_count -= 1;
+        if self.node_list[c].purify_count == 0 {
+            let mut q = self.node_list[c].u;
+            while q != c {
+                if self.node_list[q].color != node.color {
+                    self.unhide_row(q);
+                }
+                q = self.node_list[q].u;
+            }
+        }
+    }
+
+    fn commit(&mut self, p: usize) {
+        if self.node_list[p].color == 0 {
+            self.cover_col(self.node_list[p].col);
+        } else {
+            self.purify(p);
+        }
+    }
+
+    fn uncommit(&mut self, p: usize) {
+        if self.node_list[p].color == 0 {
+            self.uncover_col(self.node_list[p].col);
+        } else {
+            self.unpurify(p);
+        }
+    }
+
     pub fn solve(&mut self) -> Vec<Vec<L>> {
-        let mut partial_soln = vec![];
-        let mut solution_list = vec![];
-        self.search(&mut partial_soln, 0, &mut solution_list);
-        solution_list.iter().map(|solution| solution.iter().map(|&idx| {
-            self.row_labels[self.node_list[idx].data]
-        }).collect()).collect()
-    }
-
-    fn search(&mut self, partial_soln: &mut Vec<usize>, k: usize, solution_list: &mut Vec<Vec<usize>>) {
-        if self.node_list[0].r == 0 {
-            solution_list.push(partial_soln.clone());
-            return;
-        }
-        
-        let col = self.choose_col();
-        self.cover_col(col);
-        let mut r = self.node_list[col].d;
-        while r != col {
-            partial_soln.push(r);
-            let mut j = self.node_list[r].r;
-            while j != r {
-                self.cover_col(self.node_list[j].col);
-                j = self.node_list[j].r;
+        self.solve_with_limit(usize::MAX)
+    }
+
+    /// Visits every solution via `f`, which returns `false` to abort early.
+    pub fn solve_each(&mut self, mut f: impl FnMut(&[L]) -> bool) {
+        for solution in self.solver() {
+            if !f(&solution) {
+                break;
             }
-            self.search(partial_soln, k+1, solution_list);
-            j = self.node_list[r].l;
-            while j != r {
-                self.uncover_col(self.node_list[j].col);
-                j = self.node_list[j].l;
+        }
+    }
+
+    pub fn solver(&mut self) -> Solver<'_, L> {
+        Solver {
+            dl: self,
+            cols: Vec::new(),
+            partial_soln: Vec::new(),
+            state: SearchState::Descend,
+            done: false,
+        }
+    }
+
+    /// The first solution found, if any, without materializing the rest.
+    pub fn solve_first(&mut self) -> Option<Vec<L>> {
+        let mut first = None;
+        self.solve_each(|solution| {
+            first = Some(solution.to_vec());
+            false
+        });
+        first
+    }
+
+    /// Up to `n` solutions.
+    pub fn solve_with_limit(&mut self, n: usize) -> Vec<Vec<L>> {
+        let mut solutions = Vec::new();
+        if n == 0 {
+            return solutions;
+        }
+        self.solve_each(|solution| {
+            solutions.push(solution.to_vec());
+            solutions.len() < n
+        });
+        solutions
+    }
+
+    /// The number of solutions. Each solution is still built as a `Vec<L>` by `Solver`
+    /// and dropped straight away; only the count is kept.
+    pub fn count_solutions(&mut self) -> usize {
+        let mut count = 0;
+        self.solve_each(|_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    /// The exact cover minimizing total row weight (see `ExactCover::exact_cover_row_weight`),
+    /// found via branch-and-bound over Algorithm X. Uses the same explicit work stack as
+    /// `Solver` rather than recursion.
+    pub fn solve_min_weight(&mut self) -> Option<Vec<L>> {
+        let mut best: Option<(u64, Vec<usize>)> = None;
+        let mut cols = Vec::new();
+        let mut partial_soln = Vec::new();
+        let mut current_weight = 0u64;
+        let mut state = SearchState::Descend;
+
+        loop {
+            match state {
+                SearchState::Descend => {
+                    if self.node_list[0].r == 0 {
+                        if best.as_ref().is_none_or(|&(w, _)| current_weight < w) {
+                            best = Some((current_weight, partial_soln.clone()));
+                        }
+                        state = SearchState::Advance;
+                        continue;
+                    }
+
+                    // u64::MAX marks an infeasible branch, not just an expensive one
+                    let prune = if let Some((best_weight, _)) = best {
+                        let bound = self.lower_bound();
+                        bound == u64::MAX || current_weight.saturating_add(bound) >= best_weight
+                    } else {
+                        false
+                    };
+                    if prune {
+                        state = SearchState::Advance;
+                        continue;
+                    }
+
+                    let col = self.choose_col();
+                    self.cover_col(col);
+                    cols.push(col);
+                    let r = self.node_list[col].d;
+                    if r == col {
+                        self.uncover_col(col);
+                        cols.pop();
+                        state = SearchState::Advance;
+                    } else {
+                        self.commit_row(r);
+                        current_weight += self.row_weight(r);
+                        partial_soln.push(r);
+                    }
+                }
+                SearchState::Advance => {
+                    let r = match partial_soln.pop() {
+                        Some(r) => r,
+                        None => break,
+                    };
+                    current_weight -= self.row_weight(r);
+                    self.uncommit_row(r);
+                    let col = *cols.last().unwrap();
+                    let next_r = self.node_list[r].d;
+                    if next_r == col {
+                        self.uncover_col(col);
+                        cols.pop();
+                    } else {
+                        self.commit_row(next_r);
+                        current_weight += self.row_weight(next_r);
+                        partial_soln.push(next_r);
+                        state = SearchState::Descend;
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, soln)| soln.iter().map(|&idx| {
+            self.row_labels[self.node_list[idx].data]
+        }).collect())
+    }
+
+    fn row_weight(&self, r: usize) -> u64 {
+        self.row_weights[self.node_list[r].data]
+    }
+
+    /// Sum, over a disjoint set of still-uncovered primary columns, of each column's
+    /// cheapest remaining candidate row; never overestimates the true remaining cost.
+    /// Returns `u64::MAX` if some column has no candidate rows left at all.
+    fn lower_bound(&self) -> u64 {
+        let mut accounted_for = vec![false; self.num_cols + 1];
+        let mut bound = 0u64;
+        let mut c = self.node_list[0].r;
+        while c != 0 {
+            if !accounted_for[c] {
+                let mut cheapest: Option<u64> = None;
+                let mut i = self.node_list[c].d;
+                while i != c {
+                    let weight = self.row_weight(i);
+                    if cheapest.is_none_or(|w| weight < w) {
+                        cheapest = Some(weight);
+                    }
+                    // any column this row also covers could share it, so don't count it again
+                    let mut j = self.node_list[i].r;
+                    while j != i {
+                        accounted_for[self.node_list[j].col] = true;
+                        j = self.node_list[j].r;
+                    }
+                    i = self.node_list[i].d;
+                }
+                match cheapest {
+                    Some(weight) => {
+                        bound += weight;
+                        accounted_for[c] = true;
+                    }
+                    None => return u64::MAX,
+                }
             }
-            partial_soln.pop();
-            r = self.node_list[r].d;
+            c = self.node_list[c].r;
+        }
+        bound
+    }
+
+    fn commit_row(&mut self, r: usize) {
+        let mut j = self.node_list[r].r;
+        while j != r {
+            self.commit(j);
+            j = self.node_list[j].r;
+        }
+    }
+
+    fn uncommit_row(&mut self, r: usize) {
+        let mut j = self.node_list[r].l;
+        while j != r {
+            self.uncommit(j);
+            j = self.node_list[j].l;
         }
-        self.uncover_col(col);
     }
 
     fn choose_col(&self) -> usize {
@@ -227,6 +502,102 @@ where
     }
 }
 
+enum SearchState {
+    Descend,
+    Advance,
+}
+
+pub struct Solver<'a, L>
+where
+    L: Copy + Debug
+{
+    dl: &'a mut DancingLinks<L>,
+    cols: Vec<usize>,
+    partial_soln: Vec<usize>,
+    state: SearchState,
+    done: bool,
+}
+
+impl<'a, L> Solver<'a, L>
+where
+    L: Copy + Debug
+{
+    fn solution(&self) -> Vec<L> {
+        self.partial_soln.iter().map(|&idx| {
+            self.dl.row_labels[self.dl.node_list[idx].data]
+        }).collect()
+    }
+}
+
+impl<'a, L> Iterator for Solver<'a, L>
+where
+    L: Copy + Debug
+{
+    type Item = Vec<L>;
+
+    fn next(&mut self) -> Option<Vec<L>> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match self.state {
+                SearchState::Descend => {
+                    if self.dl.node_list[0].r == 0 {
+                        let solution = self.solution();
+                        self.state = SearchState::Advance;
+                        return Some(solution);
+                    }
+
+                    let col = self.dl.choose_col();
+                    self.dl.cover_col(col);
+                    self.cols.push(col);
+                    let r = self.dl.node_list[col].d;
+                    if r == col {
+                        self.dl.uncover_col(col);
+                        self.cols.pop();
+                        self.state = SearchState::Advance;
+                    } else {
+                        self.dl.commit_row(r);
+                        self.partial_soln.push(r);
+                    }
+                }
+                SearchState::Advance => {
+                    let r = match self.partial_soln.pop() {
+                        Some(r) => r,
+                        None => {
+                            self.done = true;
+                            return None;
+                        }
+                    };
+                    self.dl.uncommit_row(r);
+                    let col = *self.cols.last().unwrap();
+                    let next_r = self.dl.node_list[r].d;
+                    if next_r == col {
+                        self.dl.uncover_col(col);
+                        self.cols.pop();
+                    } else {
+                        self.dl.commit_row(next_r);
+                        self.partial_soln.push(next_r);
+                        self.state = SearchState::Descend;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, L> Drop for Solver<'a, L>
+where
+    L: Copy + Debug
+{
+    fn drop(&mut self) {
+        while let (Some(r), Some(col)) = (self.partial_soln.pop(), self.cols.pop()) {
+            self.dl.uncommit_row(r);
+            self.dl.uncover_col(col);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,8 +614,8 @@ mod tests {
             self.num_cols
         }
 
-        fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (usize, Vec<usize>)> + 'a> {
-            Box::new(self.data.iter().cloned().enumerate())
+        fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (usize, Vec<(usize, usize)>)> + 'a> {
+            Box::new(self.data.iter().map(|row| row.iter().map(|&col| (col, 0)).collect()).enumerate())
         }
     }
 
@@ -260,4 +631,159 @@ mod tests {
         assert_eq!(solutions[0].len(), 3);
         assert_eq!(solutions[1].len(), 3);
     }
+
+    #[test]
+    fn test_solve_streaming() {
+        let test_ec = TestEC {
+            num_cols: 6,
+            data: vec![vec![0,1], vec![1,2], vec![2,3], vec![3,4], vec![4,5], vec![0,5]],
+        };
+        let mut dl = DancingLinks::new(test_ec);
+        assert_eq!(dl.count_solutions(), 2);
+
+        let first = dl.solve_first();
+        assert!(first.is_some());
+        assert_eq!(first.unwrap().len(), 3);
+
+        let limited = dl.solve_with_limit(1);
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn test_solver_is_resumable_and_drop_safe() {
+        let test_ec = TestEC {
+            num_cols: 6,
+            data: vec![vec![0,1], vec![1,2], vec![2,3], vec![3,4], vec![4,5], vec![0,5]],
+        };
+        let mut dl = DancingLinks::new(test_ec);
+
+        {
+            let mut solver = dl.solver();
+            assert!(solver.next().is_some());
+            // dropped here, mid-search, without exhausting the solver
+        }
+
+        // the drop above must have uncovered everything it still held, so a fresh
+        // search over the same structure still finds every solution.
+        assert_eq!(dl.solve().len(), 2);
+    }
+
+    struct ColorEC {
+        num_cols: usize,
+        num_secondary: usize,
+        data: Vec<(usize, Vec<(usize, usize)>)>,
+    }
+
+    impl ExactCover for ColorEC {
+        type Label = usize;
+
+        fn exact_cover_num_cols(&self) -> usize {
+            self.num_cols
+        }
+
+        fn exact_cover_secondary_cols(&self) -> usize {
+            self.num_secondary
+        }
+
+        fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (usize, Vec<(usize, usize)>)> + 'a> {
+            Box::new(self.data.iter().cloned())
+        }
+    }
+
+    #[test]
+    fn test_xcc_purifies_disagreeing_rows() {
+        // Column 0 is primary and column 1 is secondary, so a solution only needs to
+        // cover column 0. Row 0 commits column 1 to color 1, which hides row 2
+        // (color 2) while that branch is searched.
+        let color_ec = ColorEC {
+            num_cols: 2,
+            num_secondary: 1,
+            data: vec![
+                (0, vec![(0, 0), (1, 1)]),
+                (1, vec![(1, 1)]),
+                (2, vec![(1, 2)]),
+            ],
+        };
+        let mut dl = DancingLinks::new(color_ec);
+        let solutions = dl.solve();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0], vec![0]);
+    }
+
+    #[test]
+    fn test_xcc_column_purified_by_two_agreeing_rows() {
+        // Columns 0 and 1 are primary; columns 2 and 3 are secondary. Row 1 is an
+        // alternative for column 0 that disagrees with row 0 on column 2's color, so
+        // whichever of row 0 / row 2 purifies column 2 first hides row 1 there (a real
+        // hide, unlinking row 1 from columns 0 and 3 too). The other of row 0 / row 2
+        // then purifies the very same column again with the same color: that second
+        // purify must be a no-op rather than re-hiding row 1's now-already-hidden row.
+        let color_ec = ColorEC {
+            num_cols: 4,
+            num_secondary: 2,
+            data: vec![
+                (0, vec![(0, 0), (2, 1)]),
+                (1, vec![(0, 0), (2, 2), (3, 9)]),
+                (2, vec![(1, 0), (2, 1)]),
+            ],
+        };
+        let mut dl = DancingLinks::new(color_ec);
+        let mut solutions = dl.solve();
+        assert_eq!(solutions.len(), 1);
+        solutions[0].sort();
+        assert_eq!(solutions[0], vec![0, 2]);
+    }
+
+    struct WeightedEC {
+        num_cols: usize,
+        data: Vec<Vec<usize>>,
+        weights: Vec<u64>,
+    }
+
+    impl ExactCover for WeightedEC {
+        type Label = usize;
+
+        fn exact_cover_num_cols(&self) -> usize {
+            self.num_cols
+        }
+
+        fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (usize, Vec<(usize, usize)>)> + 'a> {
+            Box::new(self.data.iter().map(|row| row.iter().map(|&col| (col, 0)).collect()).enumerate())
+        }
+
+        fn exact_cover_row_weight(&self, label: &usize) -> u64 {
+            self.weights[*label]
+        }
+    }
+
+    #[test]
+    fn test_solve_min_weight_prefers_cheaper_cover() {
+        // rows 0+1 together cover every column for weight 10; row 2 alone covers
+        // everything for weight 3, so that's the minimum-weight exact cover.
+        let weighted_ec = WeightedEC {
+            num_cols: 4,
+            data: vec![vec![0,1], vec![2,3], vec![0,1,2,3]],
+            weights: vec![5, 5, 3],
+        };
+        let mut dl = DancingLinks::new(weighted_ec);
+        let solution = dl.solve_min_weight();
+        assert_eq!(solution, Some(vec![2]));
+    }
+
+    #[test]
+    fn test_solve_min_weight_does_not_overflow_on_dead_branch() {
+        // Row 0 alone is the only exact cover. Rows 1 and 2 both touch column 3, so
+        // whichever is tried after row 0 sets `best` covers column 3 and hides the
+        // other, leaving columns 2 and 4 (or 0 and 1) with no candidate rows at all:
+        // `lower_bound` returns its infeasible sentinel while a nonzero partial weight
+        // is already committed, which must not overflow when added together.
+        let weighted_ec = WeightedEC {
+            num_cols: 5,
+            data: vec![vec![0,1,2,3,4], vec![0,1,3], vec![2,3,4]],
+            weights: vec![1, 1, 1],
+        };
+        let mut dl = DancingLinks::new(weighted_ec);
+        let solution = dl.solve_min_weight();
+        assert_eq!(solution, Some(vec![0]));
+    }
 }
@@ -0,0 +1,212 @@
+use crate::ExactCover;
+
+const BLACK: usize = 1;
+const WHITE: usize = 2;
+
+/// A row or column placement chosen by the solver: which line it fills and the
+/// index into that line's precomputed list of legal placements.
+#[derive(Clone, Copy, Debug)]
+pub enum NonogramLine {
+    Row(usize, usize),
+    Col(usize, usize),
+}
+
+/// A run-length nonogram (picross) puzzle: every row and column is clued with the
+/// lengths of its filled-cell runs, in order, and the grid is solved by exact cover
+/// with colors — one primary column per line forces exactly one placement, and the
+/// cells themselves are secondary columns colored black/white so a row's and a
+/// column's placements must agree on every cell they share.
+#[derive(Clone, Debug)]
+pub struct NonogramPuzzle {
+    rows: usize,
+    cols: usize,
+    row_placements: Vec<Vec<Vec<bool>>>,
+    col_placements: Vec<Vec<Vec<bool>>>,
+}
+
+impl NonogramPuzzle {
+
+    pub fn from_clues(row_clues: Vec<Vec<usize>>, col_clues: Vec<Vec<usize>>) -> NonogramPuzzle {
+        let rows = row_clues.len();
+        let cols = col_clues.len();
+
+        let row_placements: Vec<Vec<Vec<bool>>> = row_clues.iter()
+            .map(|clue| NonogramPuzzle::line_placements(clue, cols))
+            .collect();
+        let col_placements: Vec<Vec<Vec<bool>>> = col_clues.iter()
+            .map(|clue| NonogramPuzzle::line_placements(clue, rows))
+            .collect();
+
+        if let Some((_, clue)) = row_clues.iter().enumerate().find(|(i, _)| row_placements[*i].is_empty()) {
+            panic!("row clue {:?} cannot fit in a line of length {}", clue, cols);
+        }
+        if let Some((_, clue)) = col_clues.iter().enumerate().find(|(i, _)| col_placements[*i].is_empty()) {
+            panic!("col clue {:?} cannot fit in a line of length {}", clue, rows);
+        }
+
+        NonogramPuzzle {
+            rows,
+            cols,
+            row_placements,
+            col_placements,
+        }
+    }
+
+    /// Enumerate every legal placement of `clue`'s runs across a line of length `len`:
+    /// runs laid left to right with at least one white cell between consecutive runs
+    /// and any number of white cells at the ends (stars and bars over the slack). An
+    /// empty clue means the whole line is white; a clue that can't fit yields no
+    /// placements at all, and `from_clues` panics on such a clue.
+    fn line_placements(clue: &[usize], len: usize) -> Vec<Vec<bool>> {
+        let k = clue.len();
+        if k == 0 {
+            return vec![vec![false; len]];
+        }
+
+        let run_total: usize = clue.iter().sum();
+        if run_total + (k - 1) > len {
+            return vec![];
+        }
+        let slack = len - run_total - (k - 1);
+
+        NonogramPuzzle::gap_combos(slack, k + 1).into_iter().map(|gaps| {
+            let mut line = Vec::with_capacity(len);
+            for (i, &run) in clue.iter().enumerate() {
+                line.extend(std::iter::repeat_n(false, gaps[i]));
+                line.extend(std::iter::repeat_n(true, run));
+                if i < k - 1 {
+                    line.push(false);
+                }
+            }
+            line.extend(std::iter::repeat_n(false, gaps[k]));
+            line
+        }).collect()
+    }
+
+    /// Every way to distribute `slack` indistinguishable extra white cells across
+    /// `slots` gaps (stars and bars).
+    fn gap_combos(slack: usize, slots: usize) -> Vec<Vec<usize>> {
+        if slots == 1 {
+            return vec![vec![slack]];
+        }
+        (0..=slack).flat_map(|g| {
+            NonogramPuzzle::gap_combos(slack - g, slots - 1).into_iter().map(move |mut rest| {
+                rest.insert(0, g);
+                rest
+            })
+        }).collect()
+    }
+
+    fn row_line_col(&self, row: usize) -> usize {
+        row
+    }
+
+    fn col_line_col(&self, col: usize) -> usize {
+        self.rows + col
+    }
+
+    fn cell_col(&self, row: usize, col: usize) -> usize {
+        self.rows + self.cols + row * self.cols + col
+    }
+
+    /// Render a `DancingLinks` solution as a grid of booleans (`true` = filled).
+    pub fn solution_to_grid(&self, solution: &[NonogramLine]) -> Vec<Vec<bool>> {
+        let mut grid = vec![vec![false; self.cols]; self.rows];
+        for line in solution {
+            if let &NonogramLine::Row(row, placement) = line {
+                grid[row] = self.row_placements[row][placement].clone();
+            }
+        }
+        grid
+    }
+}
+
+impl ExactCover for NonogramPuzzle {
+    type Label = NonogramLine;
+
+    fn exact_cover_num_cols(&self) -> usize {
+        self.rows + self.cols + self.rows * self.cols
+    }
+
+    fn exact_cover_secondary_cols(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = (NonogramLine, Vec<(usize, usize)>)> + 'a> {
+        let row_rows = self.row_placements.iter().enumerate().flat_map(move |(r, placements)| {
+            placements.iter().enumerate().map(move |(p, placement)| {
+                let mut ec_row = vec![(self.row_line_col(r), 0)];
+                ec_row.extend(placement.iter().enumerate().map(|(c, &filled)| {
+                    (self.cell_col(r, c), if filled { BLACK } else { WHITE })
+                }));
+                (NonogramLine::Row(r, p), ec_row)
+            })
+        });
+
+        let col_rows = self.col_placements.iter().enumerate().flat_map(move |(c, placements)| {
+            placements.iter().enumerate().map(move |(p, placement)| {
+                let mut ec_row = vec![(self.col_line_col(c), 0)];
+                ec_row.extend(placement.iter().enumerate().map(|(r, &filled)| {
+                    (self.cell_col(r, c), if filled { BLACK } else { WHITE })
+                }));
+                (NonogramLine::Col(c, p), ec_row)
+            })
+        });
+
+        Box::new(row_rows.chain(col_rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    #[test]
+    fn test_line_placements() {
+        // A run of 2 in a line of length 4 has three placements: XX.., .XX., ..XX
+        let placements = NonogramPuzzle::line_placements(&[2], 4);
+        assert_eq!(placements.len(), 3);
+        assert!(placements.contains(&vec![true, true, false, false]));
+        assert!(placements.contains(&vec![false, true, true, false]));
+        assert!(placements.contains(&vec![false, false, true, true]));
+
+        // an empty clue only leaves the line all white
+        assert_eq!(NonogramPuzzle::line_placements(&[], 3), vec![vec![false; 3]]);
+
+        // a clue that can't fit has no placements at all
+        assert_eq!(NonogramPuzzle::line_placements(&[2, 2], 3), Vec::<Vec<bool>>::new());
+    }
+
+    #[test]
+    fn test_solve_heart() {
+        // . X X .
+        // X X X X
+        // X X X X
+        // . X X .
+        let row_clues = vec![vec![2], vec![4], vec![4], vec![2]];
+        let col_clues = vec![vec![2], vec![4], vec![4], vec![2]];
+
+        let puzzle = NonogramPuzzle::from_clues(row_clues, col_clues);
+        let mut dl = DancingLinks::new(puzzle.clone());
+        let solutions = dl.solve();
+        assert_eq!(solutions.len(), 1);
+
+        let grid = puzzle.solution_to_grid(&solutions[0]);
+        assert_eq!(grid, vec![
+            vec![false, true, true, false],
+            vec![true, true, true, true],
+            vec![true, true, true, true],
+            vec![false, true, true, false],
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot fit")]
+    fn test_from_clues_rejects_infeasible_row() {
+        // a run of 5 cannot fit in a row of length 4
+        let row_clues = vec![vec![5], vec![], vec![], vec![]];
+        let col_clues = vec![vec![], vec![], vec![], vec![]];
+        NonogramPuzzle::from_clues(row_clues, col_clues);
+    }
+}
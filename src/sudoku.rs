@@ -102,7 +102,7 @@ impl SudokuPuzzle {
 impl ExactCover for SudokuPuzzle {
     type Label = (u8,u8,u8);
 
-    fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = ((u8,u8,u8), Vec<usize>)> + 'a> {
+    fn exact_cover_rows<'a>(&'a self) -> Box<dyn Iterator<Item = ((u8,u8,u8), Vec<(usize, usize)>)> + 'a> {
         Box::new(
             // iterate over all row,col,num combinations...
             (0..self.size)
@@ -112,6 +112,8 @@ impl ExactCover for SudokuPuzzle {
             .map(move |clue| (clue, SudokuPuzzle::ec_cols(&[clue.0, clue.1, clue.2], self.size as usize)))
             // ...and remove rows that are already covered by the given clues.
             .filter(move |(_, ec_cols)| !ec_cols.iter().any(|&col| self.covered_cols.contains(col)))
+            // sudoku has no secondary columns, so every column is uncolored.
+            .map(|(clue, ec_cols)| (clue, ec_cols.into_iter().map(|col| (col, 0)).collect()))
             )
     }
 